@@ -1,14 +1,19 @@
 // Apply the stricter clippy rules to the whole module.
 #![deny(clippy::unwrap_used,clippy::expect_used,clippy::panic)]
 
+use std::collections::HashMap;
+use std::sync::Arc;
+
 use anyhow::anyhow;
 use axum::http::{HeaderMap, HeaderValue, Method, StatusCode, Uri};
 use axum::response::{IntoResponse, Response};
-use axum::{Form, Router};
+use axum::extract::Query;
+use axum::{Extension, Form, Router};
 use axum::routing::{get, post};
 use maud::{html, Markup, DOCTYPE};
 use serde::Deserialize;
-use crate::htmx::HtmxContext;
+use crate::htmx::{HtmxContext, HtmxResponseHeaders};
+use crate::i18n::{lookup, I18n, Locale};
 
 /// Our main method runs on the main tokio multi thread runtime and runs the fallible variant,
 /// printing any error to stderr.
@@ -21,6 +26,28 @@ async fn main() {
 
 /// The real main function.
 async fn main_err() -> Result<(), anyhow::Error> {
+    // The error pages registry holds a default renderer plus any per-status overrides. We register
+    // it once here and thread it into the handlers through an [Extension] so that every error, no
+    // matter where it originates, is rendered from one consistent place.
+    let mut error_pages = ErrorPages::new();
+    error_pages.register(StatusCode::NOT_FOUND, Box::new(|_code, err, locale| html! {
+        main class="container" {
+            header {
+                (render_nav_links(locale))
+                h1 { (lookup(locale, "error-not-found-heading")) }
+            }
+            section {
+                p {
+                    (err)
+                }
+            }
+        }
+    }));
+    let error_pages = Arc::new(error_pages);
+
+    // The localization bundles are compiled once and shared with every handler.
+    let i18n = Arc::new(I18n::new());
+
     let app = Router::new()
         .route("/", get(home_handler))
         .route("/fallible", get(fallible_handler))
@@ -29,55 +56,143 @@ async fn main_err() -> Result<(), anyhow::Error> {
         .route("/form-example", get(form_example))
         .route("/form-example", post(form_example_submit))
         .route("/favicon.svg", get(favicon_svg_handler))
-        // the fallback applies for 405 and 404
-        .fallback(not_found_handler);
+        // the fallback applies to unmatched paths (404); method mismatches are answered with a 405
+        // directly by axum's MethodRouter and never reach here.
+        .fallback(not_found_handler)
+        .layer(Extension(error_pages))
+        .layer(Extension(i18n));
     let listener = tokio::net::TcpListener::bind("0.0.0.0:9000").await?;
     axum::serve(listener, app).await?;
     Ok(())
 }
 
+/// A single error page renderer. Given the resolved [StatusCode], the underlying error and the
+/// negotiated [Locale] it produces the inner body [Markup] that gets wrapped by
+/// [render_body_html_or_htmx].
+type ErrorPageRenderer = Box<dyn Fn(StatusCode, &anyhow::Error, &Locale) -> Markup + Send + Sync>;
+
+/// The error pages registry. A [default](ErrorPages::default) renderer is consulted for any status
+/// that does not have a specific override registered, giving one place to customize every error
+/// screen rather than building ad-hoc markup at each call site.
+struct ErrorPages {
+    default: ErrorPageRenderer,
+    overrides: HashMap<StatusCode, ErrorPageRenderer>,
+}
+
+impl ErrorPages {
+    /// Build the registry with the built-in default "internal error" renderer and no overrides.
+    fn new() -> Self {
+        Self {
+            default: Box::new(|_code, err, locale| html! {
+                main class="container" {
+                    header {
+                        (render_nav_links(locale))
+                        h1 { (lookup(locale, "error-internal-heading")) }
+                    }
+                    section {
+                        p {
+                            (lookup(locale, "error-internal-body"))
+                        }
+                        code {
+                            (err)
+                        }
+                    }
+                }
+            }),
+            overrides: HashMap::new(),
+        }
+    }
+
+    /// Register a renderer for a specific status code, replacing any previous one.
+    fn register(&mut self, code: StatusCode, renderer: ErrorPageRenderer) {
+        self.overrides.insert(code, renderer);
+    }
+
+    /// Render the body for the given status, falling back to the default renderer.
+    fn render(&self, code: StatusCode, err: &anyhow::Error, locale: &Locale) -> Markup {
+        self.overrides.get(&code).unwrap_or(&self.default)(code, err, locale)
+    }
+}
+
 /// Our handlers return a [ResponseError] which implements [IntoResponse]. To make the errors more
 /// efficient to render, we also capture the [HtmxContext] so that we can determine whether to
 /// render the entire html or just swap in the error report content.
-#[derive(Debug)]
 struct ResponseError {
     /// This is needed so that we know whether to render the whole html or just return the boosted
     /// body content.
     htmx_context: Option<HtmxContext>,
+    /// The resolved status code used to look up the matching error page and as the status of a
+    /// full-page response (HTMX swaps are always answered with `200 OK`).
+    code: StatusCode,
+    /// The registry consulted to render the body for [Self::code].
+    error_pages: Arc<ErrorPages>,
+    /// The negotiated locale used to render the error page strings.
+    locale: Locale,
     /// The inner error.
     err: anyhow::Error,
 }
 
 impl IntoResponse for ResponseError {
     fn into_response(self) -> Response {
-        render_body_html_or_htmx(StatusCode::INTERNAL_SERVER_ERROR, "Internal Error", html! {
-            main class="container" {
-                header {
-                    (render_nav_links())
-                    h1 { "Internal error" }
-                }
-                section {
-                    p {
-                        "An internal error has occurred. Please navigate back using the links above."
-                    }
-                    code {
-                        (self.err)
-                    }
-                }
-            }
-        }, self.htmx_context)
+        let title = self.code.canonical_reason().unwrap_or("Error");
+        let inner = self.error_pages.render(self.code, &self.err, &self.locale);
+        render_body_html_or_htmx(self.code, title, inner, self.htmx_context)
     }
 }
 
-/// This trait helps to attach the [HtmxContext] to the [Result] and convert any old error into
-/// a [ResponseError]. We implement this internal trait for any [Result] type.
+/// This trait helps to attach the [HtmxContext] and [ErrorPages] registry to the [Result] and
+/// convert any old error into a [ResponseError]. We implement this internal trait for any [Result]
+/// type.
 trait CanMapToRespErr<T> {
-    fn map_resp_err(self, htmx: Option<HtmxContext>) -> Result<T, ResponseError>;
+    fn map_resp_err(self, htmx: Option<HtmxContext>, error_pages: Arc<ErrorPages>, locale: Locale) -> Result<T, ResponseError>;
 }
 
 impl<T, E> CanMapToRespErr<T> for Result<T, E> where E: Into<anyhow::Error> {
-    fn map_resp_err(self, htmx: Option<HtmxContext>) -> Result<T, ResponseError> {
-        self.map_err(|e| ResponseError{htmx_context: htmx, err: e.into()})
+    fn map_resp_err(self, htmx: Option<HtmxContext>, error_pages: Arc<ErrorPages>, locale: Locale) -> Result<T, ResponseError> {
+        self.map_err(|e| ResponseError{
+            htmx_context: htmx,
+            code: StatusCode::INTERNAL_SERVER_ERROR,
+            error_pages,
+            locale,
+            err: e.into(),
+        })
+    }
+}
+
+/// A redirect that adapts to how the request arrived. For a boosted HTMX request it emits an
+/// `HX-Location` header with a `200 OK` so HTMX performs the navigation client side; for a plain
+/// navigation it emits a standard `303 See Other` with a `Location` header. This lets a handler use
+/// the post/redirect/get pattern identically regardless of the client.
+struct Redirect {
+    to: Uri,
+    htmx_context: Option<HtmxContext>,
+}
+
+/// Construct a [Redirect] to the given target, honouring the optional [HtmxContext].
+fn redirect(to: Uri, htmx_context: Option<HtmxContext>) -> Redirect {
+    Redirect{to, htmx_context}
+}
+
+impl IntoResponse for Redirect {
+    fn into_response(self) -> Response {
+        let location = self.to.to_string();
+        if self.htmx_context.is_some() {
+            // HTMX needs a 200 response with HX-Location to navigate on a boosted request. Route it
+            // through the typed builder so header construction and validation live in one place.
+            let headers = HtmxResponseHeaders{location: Some(location), ..HtmxResponseHeaders::default()};
+            (StatusCode::OK, headers, ()).into_response()
+        } else {
+            match HeaderValue::from_str(&location) {
+                // The target is constructed by us, so an invalid header value is a programming error
+                // rather than something the client can trigger.
+                Ok(value) => {
+                    let mut hm = HeaderMap::new();
+                    hm.insert("Location", value);
+                    (StatusCode::SEE_OTHER, hm).into_response()
+                }
+                Err(_) => StatusCode::INTERNAL_SERVER_ERROR.into_response(),
+            }
+        }
     }
 }
 
@@ -119,12 +234,12 @@ fn render_body_html_or_htmx(code: StatusCode, title: impl AsRef<str>, inner: Mar
     hm.insert("Vary", HeaderValue::from_static("HX-Request"));
     if let Some(hc) = htmx_context {
         // Ensure that we retarget the request if it's attempting to swap to the wrong place.
+        let mut htmx_headers = HtmxResponseHeaders::default();
         if hc.target.is_some_and(|x| x.ne("#body")) {
-            hm.insert("HX-Retarget", HeaderValue::from_static("#body"));
-            hm.insert("HX-Reswap", HeaderValue::from_static("innerHTML"));
+            htmx_headers = htmx_headers.retarget("#body").reswap("innerHTML");
         }
         // HTMX requires HTTP 200 responses by default.
-        (StatusCode::OK, hm, html! {
+        (StatusCode::OK, hm, htmx_headers, html! {
             title { (title.as_ref()) }
             (inner)
         }.0).into_response()
@@ -134,30 +249,36 @@ fn render_body_html_or_htmx(code: StatusCode, title: impl AsRef<str>, inner: Mar
 }
 
 /// The nav links at the top of the page are always repeated.
-fn render_nav_links() -> Markup {
+fn render_nav_links(locale: &Locale) -> Markup {
     html! {
         nav {
-            a href="/" { "home "}
+            a href="/" { (lookup(locale, "nav-home")) }
             " | "
-            a href="/fallible" { "fallible" }
+            a href="/fallible" { (lookup(locale, "nav-fallible")) }
             " | "
-            a href="/does-not-exist" { "does-not-exist" }
+            a href="/does-not-exist" { (lookup(locale, "nav-does-not-exist")) }
             " | "
-            a href="/form-example" { "form-example" }
+            a href="/form-example" { (lookup(locale, "nav-form-example")) }
         }
     }
 }
 
-async fn home_handler(headers: HeaderMap) -> Result<Response, ResponseError> {
+/// Resolve the [Locale] for a request from its `Accept-Language` header.
+fn negotiate_locale(headers: &HeaderMap, i18n: &I18n) -> Locale {
+    i18n.negotiate(headers.get("Accept-Language").and_then(|v| v.to_str().ok()))
+}
+
+async fn home_handler(headers: HeaderMap, Extension(i18n): Extension<Arc<I18n>>) -> Result<Response, ResponseError> {
+    let locale = negotiate_locale(&headers, &i18n);
     Ok(render_body_html_or_htmx(StatusCode::OK, "Home page", html! {
         main class="container" {
             header {
-                (render_nav_links())
-                h1 { "Home" }
+                (render_nav_links(&locale))
+                h1 { (lookup(&locale, "home-heading")) }
             }
             section {
                 p {
-                    "This is the home page."
+                    (lookup(&locale, "home-body"))
                 }
             }
         }
@@ -174,43 +295,57 @@ async fn favicon_svg_handler() -> Result<Response, ResponseError> {
     "#).into_response())
 }
 
-async fn fallible_handler(headers: HeaderMap) -> Result<Response, ResponseError> {
+async fn fallible_handler(headers: HeaderMap, Extension(error_pages): Extension<Arc<ErrorPages>>, Extension(i18n): Extension<Arc<I18n>>) -> Result<Response, ResponseError> {
+    let locale = negotiate_locale(&headers, &i18n);
     let htmx_context = HtmxContext::try_from(headers).ok();
 
     // Produce an error response sometimes.
     if rand::random::<bool>() {
-        Err(anyhow!("request was unlucky")).map_resp_err(htmx_context.clone())?
+        Err(anyhow!("request was unlucky")).map_resp_err(htmx_context.clone(), error_pages.clone(), locale.clone())?
     }
 
     Ok(render_body_html_or_htmx(StatusCode::OK, "Lucky!", html! {
         main class="container" {
             header {
-                (render_nav_links())
-                h1 { "Lucky you" }
+                (render_nav_links(&locale))
+                h1 { (lookup(&locale, "lucky-heading")) }
             }
             section {
                 p {
-                    "You were lucky!"
+                    (lookup(&locale, "lucky-body"))
                 }
             }
         }
     }, htmx_context).into_response())
 }
 
-async fn form_example(headers: HeaderMap) -> Result<Response, ResponseError> {
+async fn form_example(headers: HeaderMap, Extension(i18n): Extension<Arc<I18n>>, Query(query): Query<FormExampleQuery>) -> Result<Response, ResponseError> {
+    let locale = negotiate_locale(&headers, &i18n);
     let htmx_context = HtmxContext::try_from(headers).ok();
+    // A successful submit redirects here with `?success=true`, so the inline success message still
+    // renders for plain navigations that never see the HX-Trigger event.
+    let success_message = query.success.then(|| lookup(&locale, "form-success"));
     Ok(render_body_html_or_htmx(StatusCode::OK, "Example form", form_example_body(
-        None, None, FormExamplePayload::default(),
+        &locale, success_message, None, FormExamplePayload::default(),
     ), htmx_context).into_response())
 }
 
-/// The page body of the form page. We use this in a few places.
-fn form_example_body(success_message: Option<String>, previous_error: Option<String>, previous_payload: FormExamplePayload) -> Markup {
+/// The query parameters accepted by the form page GET. `success` is set by the post/redirect/get
+/// flow to surface the inline success message after a redirect.
+#[derive(Debug,Default,Deserialize)]
+struct FormExampleQuery {
+    #[serde(default)]
+    success: bool,
+}
+
+/// The page body of the form page. We use this in a few places. The optional messages are the
+/// already-localized success or validation strings.
+fn form_example_body(locale: &Locale, success_message: Option<Markup>, previous_error: Option<Markup>, previous_payload: FormExamplePayload) -> Markup {
     html! {
         main class="container" {
             header {
-                (render_nav_links())
-                h1 { "Example form" }
+                (render_nav_links(locale))
+                h1 { (lookup(locale, "form-heading")) }
             }
             section {
                 @if let Some(success_message) = success_message {
@@ -227,7 +362,7 @@ fn form_example_body(success_message: Option<String>, previous_error: Option<Str
                 }
                 form action="/form-example" method="post" {
                     input type="text" name="content" value=(previous_payload.content);
-                    button type="submit" { "Submit" }
+                    button type="submit" { (lookup(locale, "form-submit")) }
                 }
             }
         }
@@ -239,62 +374,194 @@ struct FormExamplePayload {
     content: String,
 }
 
-async fn form_example_submit(headers: HeaderMap, Form(payload): Form<FormExamplePayload>) -> Result<Response, ResponseError> {
+async fn form_example_submit(headers: HeaderMap, Extension(i18n): Extension<Arc<I18n>>, Form(payload): Form<FormExamplePayload>) -> Result<Response, ResponseError> {
+    let locale = negotiate_locale(&headers, &i18n);
     let htmx_context = HtmxContext::try_from(headers).ok();
 
-    // validation of the payload
-    if let Result::<(), anyhow::Error>::Err(e) = if payload.content.is_empty() {
-        Err(anyhow!("Content is empty"))
+    // validation of the payload; the error carries the message key to localize.
+    if let Result::<(), &str>::Err(key) = if payload.content.is_empty() {
+        Err("validation-content-empty")
     } else if !payload.content.is_ascii() {
-        Err(anyhow!("Content is not ascii"))
+        Err("validation-content-not-ascii")
     } else {
         Ok(())
     } {
         // NOTE: we could optimise this by just returning the success or validation messages. But that's only
         // useful if we have expensive content on the page that we don't want to rebuild or render.
         return Ok(render_body_html_or_htmx(StatusCode::BAD_REQUEST, "Example form", form_example_body(
-            None, Some(e.to_string()), payload,
+            &locale, None, Some(lookup(&locale, key)), payload,
         ), htmx_context).into_response());
     }
 
-    Ok(render_body_html_or_htmx(StatusCode::OK, "Example form", form_example_body(
-        Some("Content was valid".to_string()), None, FormExamplePayload::default(),
-    ), htmx_context).into_response())
+    // Successful submissions redirect to a fresh GET (post/redirect/get) so a refresh does not
+    // resubmit the form. We also fire a `showMessage` event carrying the localized success text so
+    // clients listening for it can surface a toast alongside the re-rendered page the redirect
+    // produces.
+    // Use the raw localized string here, not the HTML-escaped Markup: serde_json escapes the value
+    // for the JSON payload, and a JS consumer setting `textContent` would render HTML entities
+    // literally if we double-escaped.
+    let trigger = HtmxResponseHeaders::default()
+        .trigger("showMessage", serde_json::Value::String(locale.format("form-success")));
+    Ok((trigger, redirect(Uri::from_static("/form-example?success=true"), htmx_context)).into_response())
 }
 
-async fn not_found_handler(method: Method, uri: Uri, headers: HeaderMap) -> Result<Response, ResponseError> {
+async fn not_found_handler(method: Method, uri: Uri, headers: HeaderMap, Extension(error_pages): Extension<Arc<ErrorPages>>, Extension(i18n): Extension<Arc<I18n>>) -> Response {
     let accept_html = headers.get("Accept")
         .and_then(|raw| raw.to_str().ok().map(|ct| ct.contains("text/html") || ct.contains("*/*")))
         .unwrap_or(true);
     if !accept_html {
-        return Ok(StatusCode::NOT_FOUND.into_response());
+        return StatusCode::NOT_FOUND.into_response();
     }
 
-    Ok(render_body_html_or_htmx(StatusCode::OK, "Not found", html! {
-        main class="container" {
-            header {
-                (render_nav_links())
-                h1 { "Not Found" }
-            }
-            section {
-                p {
-                    code { (method.as_str()) }
-                    " "
-                    code { (uri.path()) }
-                    " not found"
-                }
-            }
-        }
-    }, HtmxContext::try_from(headers).ok()))
+    // Render the 404 page through the registry so it shares the same layout as every other error.
+    let locale = negotiate_locale(&headers, &i18n);
+    ResponseError{
+        htmx_context: HtmxContext::try_from(headers).ok(),
+        code: StatusCode::NOT_FOUND,
+        error_pages,
+        locale,
+        err: anyhow!("{} {} not found", method.as_str(), uri.path()),
+    }.into_response()
 }
 
 /// Wrap up the [HtmxContext] capture in a submodule.
 mod htmx {
-    use axum::http::HeaderMap;
+    use axum::http::{HeaderMap, HeaderName, HeaderValue, StatusCode};
+    use axum::response::{IntoResponse, IntoResponseParts, Response, ResponseParts};
     use anyhow::{anyhow, Error};
     use url::Url;
     use std::str::FromStr;
 
+    /// A typed builder for the HTMX response header family. Handlers set the fields they need and
+    /// return the value as a response part (e.g. `(HtmxResponseHeaders, Response)`); the values are
+    /// validated into [HeaderValue]s exactly once inside [IntoResponseParts].
+    #[derive(Debug, Default)]
+    pub struct HtmxResponseHeaders {
+        /// `HX-Push-Url`: push a new URL into the browser history stack.
+        pub push_url: Option<String>,
+        /// `HX-Replace-Url`: replace the current URL in the browser history.
+        pub replace_url: Option<String>,
+        /// `HX-Reswap`: override how the response is swapped in.
+        pub reswap: Option<String>,
+        /// `HX-Retarget`: override the element the response is swapped into.
+        pub retarget: Option<String>,
+        /// `HX-Reselect`: override which part of the response is swapped in.
+        pub reselect: Option<String>,
+        /// `HX-Location`: perform a client side redirect without a full page reload.
+        pub location: Option<String>,
+        /// `HX-Refresh`: instruct the client to do a full refresh of the page.
+        pub refresh: bool,
+        /// The client-side events to fire, serialized into the `HX-Trigger` header family.
+        pub triggers: HtmxTriggers,
+    }
+
+    impl HtmxResponseHeaders {
+        /// Set the `HX-Retarget` and, conventionally alongside it, keep the other fields untouched.
+        pub fn retarget(mut self, css_selector: impl Into<String>) -> Self {
+            self.retarget = Some(css_selector.into());
+            self
+        }
+
+        /// Set the `HX-Reswap` strategy.
+        pub fn reswap(mut self, strategy: impl Into<String>) -> Self {
+            self.reswap = Some(strategy.into());
+            self
+        }
+
+        /// Fire a named client-side event immediately (the `HX-Trigger` header).
+        pub fn trigger(mut self, name: impl Into<String>, detail: serde_json::Value) -> Self {
+            self.triggers = self.triggers.trigger(name, detail);
+            self
+        }
+    }
+
+    impl IntoResponseParts for HtmxResponseHeaders {
+        type Error = HtmxResponseHeadersError;
+
+        fn into_response_parts(self, mut res: ResponseParts) -> Result<ResponseParts, Self::Error> {
+            let headers = res.headers_mut();
+            let mut set = |name: HeaderName, value: &str| -> Result<(), HtmxResponseHeadersError> {
+                headers.insert(name, HeaderValue::from_str(value).map_err(|e| HtmxResponseHeadersError(e.into()))?);
+                Ok(())
+            };
+            if let Some(v) = &self.push_url { set(HeaderName::from_static("hx-push-url"), v)?; }
+            if let Some(v) = &self.replace_url { set(HeaderName::from_static("hx-replace-url"), v)?; }
+            if let Some(v) = &self.reswap { set(HeaderName::from_static("hx-reswap"), v)?; }
+            if let Some(v) = &self.retarget { set(HeaderName::from_static("hx-retarget"), v)?; }
+            if let Some(v) = &self.reselect { set(HeaderName::from_static("hx-reselect"), v)?; }
+            if let Some(v) = &self.location { set(HeaderName::from_static("hx-location"), v)?; }
+            if self.refresh { set(HeaderName::from_static("hx-refresh"), "true")?; }
+            for (name, value) in self.triggers.serialize().map_err(|e| HtmxResponseHeadersError(e.into()))? {
+                set(name, &value)?;
+            }
+            Ok(res)
+        }
+    }
+
+    /// Accumulates server-driven client events to be pushed back through the `HX-Trigger`,
+    /// `HX-Trigger-After-Settle` and `HX-Trigger-After-Swap` response headers. Each event carries an
+    /// optional JSON detail payload; an empty set serializes to no headers at all.
+    #[derive(Debug, Default)]
+    pub struct HtmxTriggers {
+        /// Events fired as soon as the response is received (`HX-Trigger`).
+        receive: Vec<(String, serde_json::Value)>,
+        /// Events fired after the swapped content has settled (`HX-Trigger-After-Settle`).
+        after_settle: Vec<(String, serde_json::Value)>,
+        /// Events fired after the swap but before settling (`HX-Trigger-After-Swap`).
+        after_swap: Vec<(String, serde_json::Value)>,
+    }
+
+    impl HtmxTriggers {
+        /// Queue an event on the `HX-Trigger` header.
+        pub fn trigger(mut self, name: impl Into<String>, detail: serde_json::Value) -> Self {
+            self.receive.push((name.into(), detail));
+            self
+        }
+
+        /// Queue an event on the `HX-Trigger-After-Settle` header.
+        pub fn trigger_after_settle(mut self, name: impl Into<String>, detail: serde_json::Value) -> Self {
+            self.after_settle.push((name.into(), detail));
+            self
+        }
+
+        /// Queue an event on the `HX-Trigger-After-Swap` header.
+        pub fn trigger_after_swap(mut self, name: impl Into<String>, detail: serde_json::Value) -> Self {
+            self.after_swap.push((name.into(), detail));
+            self
+        }
+
+        /// Serialize each non-empty timing group into its header. The detail map is emitted as a
+        /// single JSON object (e.g. `{"showMessage":"hi"}`) so event names and payloads are escaped
+        /// safely rather than relying on the ambiguous comma-separated form.
+        fn serialize(&self) -> Result<Vec<(HeaderName, String)>, serde_json::Error> {
+            let groups = [
+                (HeaderName::from_static("hx-trigger"), &self.receive),
+                (HeaderName::from_static("hx-trigger-after-settle"), &self.after_settle),
+                (HeaderName::from_static("hx-trigger-after-swap"), &self.after_swap),
+            ];
+            let mut out = Vec::new();
+            for (header, events) in groups {
+                if events.is_empty() {
+                    continue;
+                }
+                let map = events.iter().cloned().collect::<serde_json::Map<String, serde_json::Value>>();
+                out.push((header, serde_json::to_string(&map)?));
+            }
+            Ok(out)
+        }
+    }
+
+    /// Returned as the error part when one of the [HtmxResponseHeaders] values cannot be encoded as
+    /// a valid [HeaderValue]. This should only happen for values a handler constructs badly.
+    #[derive(Debug)]
+    pub struct HtmxResponseHeadersError(Error);
+
+    impl IntoResponse for HtmxResponseHeadersError {
+        fn into_response(self) -> Response {
+            (StatusCode::INTERNAL_SERVER_ERROR, self.0.to_string()).into_response()
+        }
+    }
+
     #[derive(Debug,Default,Clone,PartialEq,Eq,PartialOrd,Ord)]
     pub struct HtmxContext {
         pub(crate) is_boost: bool,
@@ -335,4 +602,144 @@ mod htmx {
             }
         }
     }
+}
+
+/// The localization subsystem. Message bundles are compiled in at startup (one [FluentResource] per
+/// language) and the request `Accept-Language` header is negotiated against them, falling back to a
+/// default locale for unknown languages or missing keys.
+mod i18n {
+    // This module must never panic on a missing translation key.
+    #![deny(clippy::panic)]
+    use std::collections::HashMap;
+    use std::sync::Arc;
+
+    use fluent::bundle::FluentBundle;
+    use fluent::{FluentArgs, FluentResource};
+    use intl_memoizer::concurrent::IntlLangMemoizer;
+    use maud::{html, Markup};
+    use unic_langid::LanguageIdentifier;
+
+    /// The thread-safe flavour of [FluentBundle]; the concurrent memoizer lets us share compiled
+    /// bundles across tokio worker threads behind an [Arc].
+    type Bundle = FluentBundle<FluentResource, IntlLangMemoizer>;
+
+    /// The default locale. Its bundle must define every key, since it is the final fallback.
+    const DEFAULT_LANG: &str = "en";
+
+    /// The compiled-in message sources, keyed by BCP-47 language tag.
+    const SOURCES: &[(&str, &str)] = &[
+        ("en", include_str!("locales/en.ftl")),
+        ("fr", include_str!("locales/fr.ftl")),
+    ];
+
+    /// The registry of compiled bundles. Construct it once with [I18n::new] and share it through an
+    /// `Extension`.
+    pub struct I18n {
+        bundles: HashMap<LanguageIdentifier, Arc<Bundle>>,
+        default: Arc<Bundle>,
+    }
+
+    impl I18n {
+        /// Compile every bundle in [SOURCES]. Sources that fail to parse are skipped rather than
+        /// aborting startup; the default locale is guaranteed to exist (empty if its source is
+        /// broken) so that [negotiate](Self::negotiate) always has a fallback.
+        pub fn new() -> Self {
+            let mut bundles: HashMap<LanguageIdentifier, Arc<Bundle>> = HashMap::new();
+            for (lang, src) in SOURCES {
+                let Ok(langid) = lang.parse::<LanguageIdentifier>() else { continue };
+                let Ok(resource) = FluentResource::try_new(src.to_string()) else { continue };
+                let mut bundle = FluentBundle::new_concurrent(vec![langid.clone()]);
+                // Keep the output free of the bidi isolation marks that Fluent inserts by default,
+                // since our values are interpolated straight into html.
+                bundle.set_use_isolating(false);
+                if bundle.add_resource(resource).is_ok() {
+                    bundles.insert(langid, Arc::new(bundle));
+                }
+            }
+            let default_lid: LanguageIdentifier = DEFAULT_LANG.parse().unwrap_or_default();
+            let default = bundles.get(&default_lid).cloned().unwrap_or_else(|| {
+                Arc::new(FluentBundle::new_concurrent(vec![default_lid.clone()]))
+            });
+            Self{bundles, default}
+        }
+
+        /// Negotiate the raw `Accept-Language` header into a [Locale]. The header is parsed into an
+        /// ordered list of language identifiers and the first one we have a bundle for wins; the
+        /// default locale is always carried as the fallback.
+        pub fn negotiate(&self, accept_language: Option<&str>) -> Locale {
+            let primary = accept_language
+                .map(parse_accept_language)
+                .and_then(|langs| langs.into_iter().find_map(|lid| self.lookup_bundle(&lid)))
+                .unwrap_or_else(|| self.default.clone());
+            Locale{primary, default: self.default.clone()}
+        }
+
+        /// Resolve a requested language to a bundle: an exact match first, then the base language so
+        /// that region-qualified tags (e.g. `fr-FR`, `fr-CA`) fall through to their `fr` bundle.
+        fn lookup_bundle(&self, lid: &LanguageIdentifier) -> Option<Arc<Bundle>> {
+            if let Some(bundle) = self.bundles.get(lid) {
+                return Some(bundle.clone());
+            }
+            self.bundles.iter()
+                .find(|(key, _)| key.language == lid.language)
+                .map(|(_, bundle)| bundle.clone())
+        }
+    }
+
+    /// A resolved locale: the negotiated bundle plus the default bundle used for missing keys.
+    #[derive(Clone)]
+    pub struct Locale {
+        primary: Arc<Bundle>,
+        default: Arc<Bundle>,
+    }
+
+    impl Locale {
+        /// Look up a message, trying the primary bundle then the default, and returning the key
+        /// itself if neither defines it. This never panics.
+        pub fn format(&self, key: &str) -> String {
+            for bundle in [self.primary.as_ref(), self.default.as_ref()] {
+                if let Some(pattern) = bundle.get_message(key).and_then(|m| m.value()) {
+                    let mut errors = Vec::new();
+                    let value = bundle.format_pattern(pattern, None::<&FluentArgs>, &mut errors);
+                    if errors.is_empty() {
+                        return value.into_owned();
+                    }
+                }
+            }
+            key.to_string()
+        }
+    }
+
+    /// Look up a localized string for `key` in the given [Locale], returning it as escaped [Markup].
+    pub fn lookup(locale: &Locale, key: &str) -> Markup {
+        html! { (locale.format(key)) }
+    }
+
+    /// Parse an `Accept-Language` header value into an ordered list of language identifiers. The
+    /// `q` weights are honoured for ordering; entries whose language tag does not parse, `*` and
+    /// `q=0` ("not acceptable", per RFC 7231) are skipped. A missing or unparsable `q` counts as 1.
+    fn parse_accept_language(header: &str) -> Vec<LanguageIdentifier> {
+        let mut weighted: Vec<(f32, LanguageIdentifier)> = header
+            .split(',')
+            .filter_map(|part| {
+                let mut pieces = part.split(';');
+                let tag = pieces.next()?.trim();
+                if tag.is_empty() || tag == "*" {
+                    return None;
+                }
+                let langid = tag.parse::<LanguageIdentifier>().ok()?;
+                let weight = pieces
+                    .find_map(|p| p.trim().strip_prefix("q=").and_then(|q| q.parse::<f32>().ok()))
+                    .unwrap_or(1.0);
+                // A weight of zero (or below) means the client explicitly rejects the language.
+                if weight <= 0.0 {
+                    return None;
+                }
+                Some((weight, langid))
+            })
+            .collect();
+        // Stable sort by descending weight to preserve the original order within equal weights.
+        weighted.sort_by(|a, b| b.0.total_cmp(&a.0));
+        weighted.into_iter().map(|(_, lid)| lid).collect()
+    }
 }
\ No newline at end of file